@@ -1,3 +1,5 @@
+use std::convert::TryInto;
+
 use anyhow::Result;
 use yasna::Tag;
 use yasna::models::ObjectIdentifier as Oid;
@@ -7,7 +9,262 @@ pub fn list_cert_extensions(cert_pem :&str) -> Result<Vec<Oid>> {
 	Ok(list_cert_extensions_der(&der.contents)?)
 }
 
-fn push_cert_extensions(tbs_cert_reader :yasna::BERReader, oids :&mut Vec<Oid>) -> yasna::ASN1Result<()> {
+// id-ce-nameConstraints in
+// https://tools.ietf.org/html/rfc5280#section-4.2.1.10
+pub const OID_NAME_CONSTRAINTS :&[u64] = &[2, 5, 29, 30];
+
+// id-ce-subjectAltName in
+// https://tools.ietf.org/html/rfc5280#section-4.2.1.6
+pub const OID_SUBJECT_ALT_NAME :&[u64] = &[2, 5, 29, 17];
+
+/// GeneralName as defined in
+/// https://tools.ietf.org/html/rfc5280#section-4.2.1.6
+///
+/// We don't support every choice of the upstream type,
+/// only the ones that show up in name constraints / SANs
+/// in practice. Anything else ends up in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneralName {
+	Rfc822Name(String),
+	DnsName(String),
+	/// The raw DER of the wrapped `Name`. We don't decode RDNs any further.
+	DirectoryName(Vec<u8>),
+	Uri(String),
+	IpAddress(Vec<u8>),
+	RegisteredId(Oid),
+	Other(u8, Vec<u8>),
+}
+
+pub struct GeneralSubtree {
+	pub base :GeneralName,
+	pub minimum :u64,
+	pub maximum :Option<u64>,
+}
+
+/// NameConstraints as defined in
+/// https://tools.ietf.org/html/rfc5280#section-4.2.1.10
+pub struct NameConstraints {
+	pub permitted_subtrees :Vec<GeneralSubtree>,
+	pub excluded_subtrees :Vec<GeneralSubtree>,
+}
+
+fn read_general_name(rdr :yasna::BERReader) -> yasna::ASN1Result<GeneralName> {
+	let tag_number = rdr.lookahead_tag()?.tag_number;
+	Ok(match tag_number {
+		1 => GeneralName::Rfc822Name(read_ia5_string(rdr, 1)?),
+		2 => GeneralName::DnsName(read_ia5_string(rdr, 2)?),
+		// Name is itself a CHOICE, and CHOICE fields are always
+		// explicitly tagged regardless of the module's tagging default.
+		4 => GeneralName::DirectoryName(rdr.read_tagged(Tag::context(4), |rdr| rdr.read_der())?),
+		6 => GeneralName::Uri(read_ia5_string(rdr, 6)?),
+		7 => GeneralName::IpAddress(rdr.read_tagged_implicit(Tag::context(7), |rdr| rdr.read_bytes())?),
+		8 => GeneralName::RegisteredId(rdr.read_tagged_implicit(Tag::context(8), |rdr| rdr.read_oid())?),
+		n => {
+			let n :u8 = n.try_into().unwrap_or(0xff);
+			// Unlike the named choices above, we don't know whether this one
+			// is primitive (e.g. x400Address) or constructed (e.g. otherName,
+			// ediPartyName) under the hood, so read it generically via
+			// read_tagged_der() instead of assuming read_bytes() (primitive
+			// only -- it errors in DER mode on constructed content).
+			GeneralName::Other(n, rdr.read_tagged_der()?.value().to_owned())
+		},
+	})
+}
+
+fn read_ia5_string(rdr :yasna::BERReader, tag_number :u64) -> yasna::ASN1Result<String> {
+	let bytes = rdr.read_tagged_implicit(Tag::context(tag_number), |rdr| rdr.read_bytes())?;
+	Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn read_general_subtree(rdr :yasna::BERReader) -> yasna::ASN1Result<GeneralSubtree> {
+	rdr.read_sequence(|rdr| {
+		let base = read_general_name(rdr.next())?;
+		let minimum = rdr.read_optional(|rdr| {
+			rdr.read_tagged_implicit(Tag::context(0), |rdr| rdr.read_u64())
+		})?.unwrap_or(0);
+		let maximum = rdr.read_optional(|rdr| {
+			rdr.read_tagged_implicit(Tag::context(1), |rdr| rdr.read_u64())
+		})?;
+		Ok(GeneralSubtree { base, minimum, maximum })
+	})
+}
+
+fn read_general_subtrees(rdr :yasna::BERReader, tag_number :u64) -> yasna::ASN1Result<Vec<GeneralSubtree>> {
+	rdr.read_tagged_implicit(Tag::context(tag_number), |rdr| {
+		let mut subtrees = Vec::new();
+		rdr.read_sequence_of(|rdr| {
+			subtrees.push(read_general_subtree(rdr)?);
+			Ok(())
+		})?;
+		Ok(subtrees)
+	})
+}
+
+/// Decodes the `extnValue` OCTET STRING contents of a
+/// `2.5.29.30` (id-ce-nameConstraints) extension.
+pub fn parse_name_constraints_der(extn_value :&[u8]) -> Result<NameConstraints> {
+	let (permitted_subtrees, excluded_subtrees) = yasna::parse_der(extn_value, |rdr| {
+		rdr.read_sequence(|rdr| {
+			let permitted = rdr.read_optional(|rdr| read_general_subtrees(rdr, 0))?;
+			let excluded = rdr.read_optional(|rdr| read_general_subtrees(rdr, 1))?;
+			Ok((permitted.unwrap_or_default(), excluded.unwrap_or_default()))
+		})
+	})?;
+	Ok(NameConstraints { permitted_subtrees, excluded_subtrees })
+}
+
+/// Whether any of the permitted or excluded subtrees of `nc` constrain
+/// a `dNSName` that touches `domain`: an exact match, a subdomain of
+/// `domain`, or a parent domain of it.
+pub fn name_constraints_touch_domain(nc :&NameConstraints, domain :&str) -> bool {
+	let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+	let touches = |base :&GeneralName| -> bool {
+		let name = match base {
+			GeneralName::DnsName(name) => name,
+			_ => return false,
+		};
+		let name = name.trim_start_matches('.').to_ascii_lowercase();
+		domain == name || domain.ends_with(&format!(".{}", name)) || name.ends_with(&format!(".{}", domain))
+	};
+	nc.permitted_subtrees.iter().any(|st| touches(&st.base))
+		|| nc.excluded_subtrees.iter().any(|st| touches(&st.base))
+}
+
+/// Decodes the `extnValue` OCTET STRING contents of a
+/// `2.5.29.17` (id-ce-subjectAltName) extension.
+pub fn parse_subject_alt_names_der(extn_value :&[u8]) -> Result<Vec<GeneralName>> {
+	let names = yasna::parse_der(extn_value, |rdr| {
+		let mut names = Vec::new();
+		rdr.read_sequence_of(|rdr| {
+			names.push(read_general_name(rdr)?);
+			Ok(())
+		})?;
+		Ok(names)
+	})?;
+	Ok(names)
+}
+
+/// Whether any `dNSName` among `names` matches `pattern`. This is a
+/// subdomain/suffix search, not TLS wildcard matching: a leading `*.` on
+/// either side matches that name or any of its subdomains (so `*.example.com`
+/// matches `example.com`, `a.example.com` and `a.b.example.com` alike),
+/// which is broader than what a TLS client would accept for a `*.example.com`
+/// SAN. That's intentional here -- this function is for finding certs
+/// covering a domain, not for validating a TLS handshake.
+pub fn dns_names_match(names :&[GeneralName], pattern :&str) -> bool {
+	let pattern = pattern.to_ascii_lowercase();
+	names.iter().any(|name| {
+		let name = match name {
+			GeneralName::DnsName(name) => name.to_ascii_lowercase(),
+			_ => return false,
+		};
+		if let Some(suffix) = pattern.strip_prefix("*.") {
+			return name == suffix || name.ends_with(&format!(".{}", suffix));
+		}
+		if let Some(suffix) = name.strip_prefix("*.") {
+			return pattern == suffix || pattern.ends_with(&format!(".{}", suffix));
+		}
+		name == pattern
+	})
+}
+
+// id-ce-cRLDistributionPoints in
+// https://tools.ietf.org/html/rfc5280#section-4.2.1.13
+pub const OID_CRL_DISTRIBUTION_POINTS :&[u64] = &[2, 5, 29, 31];
+
+fn read_distribution_point(rdr :yasna::BERReader) -> yasna::ASN1Result<Vec<GeneralName>> {
+	rdr.read_sequence(|rdr| {
+		// distributionPoint [0] DistributionPointName, EXPLICIT since
+		// DistributionPointName is itself a CHOICE.
+		let full_names = rdr.read_optional(|rdr| {
+			rdr.read_tagged(Tag::context(0), |rdr| {
+				// fullName [0] GeneralNames, IMPLICIT
+				rdr.read_tagged_implicit(Tag::context(0), |rdr| {
+					let mut names = Vec::new();
+					rdr.read_sequence_of(|rdr| {
+						names.push(read_general_name(rdr)?);
+						Ok(())
+					})?;
+					Ok(names)
+				})
+			})
+		})?;
+		// reasons [1] and cRLIssuer [2]: not needed here, drain whatever's left
+		rdr.read_optional(|rdr| rdr.read_der())?;
+		rdr.read_optional(|rdr| rdr.read_der())?;
+		Ok(full_names.unwrap_or_default())
+	})
+}
+
+/// Decodes the `extnValue` OCTET STRING contents of a `2.5.29.31`
+/// (id-ce-cRLDistributionPoints) extension, returning the `fullName`
+/// `GeneralName`s of every distribution point (usually a single
+/// `uniformResourceIdentifier` pointing at the CRL). We don't support
+/// `nameRelativeToCRLIssuer`, and ignore the `reasons` and `cRLIssuer`
+/// fields, since in practice CAs emit one unconditional CRL per point.
+pub fn parse_crl_distribution_points_der(extn_value :&[u8]) -> Result<Vec<GeneralName>> {
+	let names = yasna::parse_der(extn_value, |rdr| {
+		let mut names = Vec::new();
+		rdr.read_sequence_of(|rdr| {
+			names.extend(read_distribution_point(rdr)?);
+			Ok(())
+		})?;
+		Ok(names)
+	})?;
+	Ok(names)
+}
+
+/// Strips the tag and length octets off a DER-encoded INTEGER, leaving
+/// just its content octets. Used to compare serial numbers byte-for-byte
+/// without pulling in a bignum dependency.
+pub(crate) fn integer_content(der :&[u8]) -> Vec<u8> {
+	if der.len() < 2 {
+		return Vec::new();
+	}
+	let len_byte = der[1];
+	let content_start = if len_byte & 0x80 == 0 {
+		2
+	} else {
+		2 + (len_byte & 0x7f) as usize
+	};
+	der.get(content_start..).unwrap_or(&[]).to_vec()
+}
+
+fn read_tbs_serial(tbs_cert_reader :yasna::BERReader) -> yasna::ASN1Result<Vec<u8>> {
+	tbs_cert_reader.read_sequence(|rdr| {
+		// version
+		rdr.next().read_der()?;
+		// serialNumber
+		let serial = rdr.next().read_der()?;
+		Ok(integer_content(&serial))
+	})
+}
+
+/// Extracts the raw `serialNumber` octets of an X.509 `Certificate` DER,
+/// for comparison against CRL `userCertificate` entries.
+pub fn cert_serial_number_der(cert_der :&[u8]) -> Result<Vec<u8>> {
+	let serial = yasna::parse_der(cert_der, |rdr| {
+		rdr.read_sequence(|rdr| {
+			let serial = read_tbs_serial(rdr.next())?;
+			// signatureAlgorithm
+			rdr.next().read_der()?;
+			// signature
+			rdr.next().read_der()?;
+			Ok(serial)
+		})
+	})?;
+	Ok(serial)
+}
+
+/// Extracts the raw `serialNumber` octets of a CT precert's
+/// `TBSCertificate` DER (the precert log entry's `tbs_certificate`, not
+/// wrapped in an outer `Certificate`).
+pub fn pre_cert_serial_number_der(tbs_der :&[u8]) -> Result<Vec<u8>> {
+	let serial = yasna::parse_der(tbs_der, read_tbs_serial)?;
+	Ok(serial)
+}
+
+fn push_cert_extensions(tbs_cert_reader :yasna::BERReader, f :&mut impl FnMut(Oid, bool, &[u8]) -> yasna::ASN1Result<()>) -> yasna::ASN1Result<()> {
 	tbs_cert_reader.read_sequence(|rdr| {
 		// version
 		rdr.next().read_der()?;
@@ -35,17 +292,16 @@ fn push_cert_extensions(tbs_cert_reader :yasna::BERReader, oids :&mut Vec<Oid>)
 							let ext = rdr.read_der()?;
 							yasna::parse_der(&ext, |rdr| {
 								rdr.read_sequence(|rdr| {
-									oids.push(rdr.next().read_oid()?);
+									let oid = rdr.next().read_oid()?;
 									let r = rdr.next();
-									if r.lookahead_tag()? == yasna::tags::TAG_BOOLEAN {
-										// critical
-										r.read_der()?;
-										// extnValue
-										rdr.next().read_bytes()?;
+									let (critical, extn_value) = if r.lookahead_tag()? == yasna::tags::TAG_BOOLEAN {
+										let critical = r.read_bool()?;
+										let extn_value = rdr.next().read_bytes()?;
+										(critical, extn_value)
 									} else {
-										// extnValue
-										r.read_bytes()?;
-									}
+										(false, r.read_bytes()?)
+									};
+									f(oid, critical, &extn_value)?;
 									Ok(())
 								})
 							})?;
@@ -65,7 +321,7 @@ fn push_cert_extensions(tbs_cert_reader :yasna::BERReader, oids :&mut Vec<Oid>)
 pub fn list_pre_cert_extensions_der(cert_der :&[u8]) -> Result<Vec<Oid>> {
 	let mut oids = Vec::new();
 	yasna::parse_der(cert_der, |rdr| {
-		push_cert_extensions(rdr, &mut oids)?;
+		push_cert_extensions(rdr, &mut |oid, _critical, _value| { oids.push(oid); Ok(()) })?;
 		Ok(())
 	})?;
 	Ok(oids)
@@ -75,7 +331,7 @@ pub fn list_cert_extensions_der(cert_der :&[u8]) -> Result<Vec<Oid>> {
 	let mut oids = Vec::new();
 	yasna::parse_der(cert_der, |rdr| {
 		rdr.read_sequence(|rdr| {
-			push_cert_extensions(rdr.next(), &mut oids)?;
+			push_cert_extensions(rdr.next(), &mut |oid, _critical, _value| { oids.push(oid); Ok(()) })?;
 			// signatureAlgorithm
 			rdr.next().read_der()?;
 			// signature
@@ -86,6 +342,95 @@ pub fn list_cert_extensions_der(cert_der :&[u8]) -> Result<Vec<Oid>> {
 	Ok(oids)
 }
 
+/// An extension's OID together with its `critical` flag, without the
+/// (possibly large) `extnValue` payload.
+pub struct ExtensionInfo {
+	pub oid :Oid,
+	pub critical :bool,
+}
+
+pub fn list_pre_cert_extension_infos_der(cert_der :&[u8]) -> Result<Vec<ExtensionInfo>> {
+	let mut exts = Vec::new();
+	yasna::parse_der(cert_der, |rdr| {
+		push_cert_extensions(rdr, &mut |oid, critical, _value| { exts.push(ExtensionInfo { oid, critical }); Ok(()) })?;
+		Ok(())
+	})?;
+	Ok(exts)
+}
+
+pub fn list_cert_extension_infos_der(cert_der :&[u8]) -> Result<Vec<ExtensionInfo>> {
+	let mut exts = Vec::new();
+	yasna::parse_der(cert_der, |rdr| {
+		rdr.read_sequence(|rdr| {
+			push_cert_extensions(rdr.next(), &mut |oid, critical, _value| { exts.push(ExtensionInfo { oid, critical }); Ok(()) })?;
+			// signatureAlgorithm
+			rdr.next().read_der()?;
+			// signature
+			rdr.next().read_der()?;
+			Ok(())
+		})
+	})?;
+	Ok(exts)
+}
+
+/// A short, human-readable label for the well-known certificate extension
+/// OIDs this crate cares about. Returns `None` for anything else.
+pub fn oid_label(oid :&Oid) -> Option<&'static str> {
+	Some(match oid.components().as_slice() {
+		[2, 5, 29, 14] => "subjectKeyIdentifier",
+		[2, 5, 29, 15] => "keyUsage",
+		[2, 5, 29, 17] => "subjectAltName",
+		[2, 5, 29, 18] => "issuerAltName",
+		[2, 5, 29, 19] => "basicConstraints",
+		[2, 5, 29, 30] => "nameConstraints",
+		[2, 5, 29, 31] => "cRLDistributionPoints",
+		[2, 5, 29, 32] => "certificatePolicies",
+		[2, 5, 29, 35] => "authorityKeyIdentifier",
+		[2, 5, 29, 37] => "extKeyUsage",
+		[1, 3, 6, 1, 5, 5, 7, 1, 1] => "authorityInfoAccess",
+		[1, 3, 6, 1, 4, 1, 11129, 2, 4, 2] => "signedCertificateTimestampList",
+		_ => return None,
+	})
+}
+
+/// Finds the `extnValue` of the first extension matching `oid` in a
+/// X.509 `Certificate` DER, if present.
+pub fn find_extension_der(cert_der :&[u8], oid :&[u64]) -> Result<Option<Vec<u8>>> {
+	let mut found = None;
+	yasna::parse_der(cert_der, |rdr| {
+		rdr.read_sequence(|rdr| {
+			push_cert_extensions(rdr.next(), &mut |ext_oid, _critical, value| {
+				if found.is_none() && ext_oid.components() == oid {
+					found = Some(value.to_owned());
+				}
+				Ok(())
+			})?;
+			// signatureAlgorithm
+			rdr.next().read_der()?;
+			// signature
+			rdr.next().read_der()?;
+			Ok(())
+		})
+	})?;
+	Ok(found)
+}
+
+/// Finds the `extnValue` of the first extension matching `oid` in a
+/// CT `PreCert` TBSCertificate DER, if present.
+pub fn find_pre_cert_extension_der(cert_der :&[u8], oid :&[u64]) -> Result<Option<Vec<u8>>> {
+	let mut found = None;
+	yasna::parse_der(cert_der, |rdr| {
+		push_cert_extensions(rdr, &mut |ext_oid, _critical, value| {
+			if found.is_none() && ext_oid.components() == oid {
+				found = Some(value.to_owned());
+			}
+			Ok(())
+		})?;
+		Ok(())
+	})?;
+	Ok(found)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -98,4 +443,64 @@ const RCGEN_TEST_CERT :&str = include_str!("rcgen-example.pem");
 		assert_eq!(oids, &[Oid::from_slice(&[2, 5, 29, 17])]);
 		Ok(())
 	}
+
+	/// Builds a DER tag-length-value triplet for short-form lengths (< 128
+	/// bytes of content), which is all these tests need.
+	fn der_tlv(tag :u8, content :&[u8]) -> Vec<u8> {
+		assert!(content.len() < 128);
+		let mut out = vec![tag, content.len() as u8];
+		out.extend_from_slice(content);
+		out
+	}
+
+	// Regression test for a NameConstraints extension that carries only
+	// excludedSubtrees, the common case that `rdr.next().read_optional(...)`
+	// used to break (it unconditionally consumed the permittedSubtrees
+	// slot before the OPTIONAL check ever ran).
+	#[test]
+	fn name_constraints_excluded_only() -> Result<()> {
+		let dns_name = der_tlv(0x82, b"example.com"); // dNSName [2] IMPLICIT IA5String
+		let base_only_subtree = der_tlv(0x30, &dns_name); // GeneralSubtree { base, no minimum/maximum }
+		let excluded_subtrees = der_tlv(0xa1, &base_only_subtree); // excludedSubtrees [1] IMPLICIT
+		let extn_value = der_tlv(0x30, &excluded_subtrees); // NameConstraints, no permittedSubtrees
+
+		let nc = parse_name_constraints_der(&extn_value)?;
+		assert!(nc.permitted_subtrees.is_empty());
+		assert_eq!(nc.excluded_subtrees.len(), 1);
+		assert!(name_constraints_touch_domain(&nc, "example.com"));
+		assert!(name_constraints_touch_domain(&nc, "sub.example.com"));
+		assert!(!name_constraints_touch_domain(&nc, "other.com"));
+		Ok(())
+	}
+
+	#[test]
+	fn san_dns_names_match() -> Result<()> {
+		let dns_name = der_tlv(0x82, b"example.com"); // dNSName [2] IMPLICIT IA5String
+		let extn_value = der_tlv(0x30, &dns_name); // SubjectAltName ::= GeneralNames
+
+		let names = parse_subject_alt_names_der(&extn_value)?;
+		assert_eq!(names, vec![GeneralName::DnsName("example.com".to_owned())]);
+		assert!(dns_names_match(&names, "example.com"));
+		assert!(dns_names_match(&names, "EXAMPLE.COM"));
+		assert!(!dns_names_match(&names, "sub.example.com"));
+		assert!(!dns_names_match(&names, "other.com"));
+
+		// A pattern of `*.example.com` is a subdomain/suffix search, not TLS
+		// wildcard matching: it also matches the bare apex.
+		assert!(dns_names_match(&names, "*.example.com"));
+		Ok(())
+	}
+
+	#[test]
+	fn crl_distribution_points_single_uri() -> Result<()> {
+		let uri = der_tlv(0x86, b"http://example.com/crl"); // uniformResourceIdentifier [6] IMPLICIT
+		let full_name = der_tlv(0xa0, &uri); // fullName [0] IMPLICIT GeneralNames
+		let distribution_point_name = der_tlv(0xa0, &full_name); // distributionPoint [0] EXPLICIT
+		let distribution_point = der_tlv(0x30, &distribution_point_name); // DistributionPoint
+		let extn_value = der_tlv(0x30, &distribution_point); // CRLDistributionPoints
+
+		let names = parse_crl_distribution_points_der(&extn_value)?;
+		assert_eq!(names, vec![GeneralName::Uri("http://example.com/crl".to_owned())]);
+		Ok(())
+	}
 }
@@ -0,0 +1,148 @@
+//! Downloading and parsing Certificate Revocation Lists (CRLs), to check
+//! whether a given certificate serial number has been revoked.
+//! See https://tools.ietf.org/html/rfc5280#section-5.1 for the format.
+
+use anyhow::Result;
+use yasna::Tag;
+use yasna::tags::{TAG_UTCTIME, TAG_GENERALIZEDTIME};
+
+use crate::cert_ext;
+
+/// Fetches the CRL at `url` and checks whether `serial` (the raw
+/// `serialNumber` octets, as returned by `cert_ext::cert_serial_number_der`
+/// / `cert_ext::pre_cert_serial_number_der`) appears among its revoked
+/// certificates. We don't verify the CRL's own signature, so this should
+/// only be used as a hint, not as an authoritative revocation check.
+pub fn is_revoked(client :&reqwest::blocking::Client, url :&str, serial :&[u8]) -> Result<bool> {
+	let revoked = fetch_revoked_serials(client, url)?;
+	Ok(revoked.iter().any(|s| s == serial))
+}
+
+/// Downloads the DER-encoded `CertificateList` at `url`.
+fn fetch_revoked_serials(client :&reqwest::blocking::Client, url :&str) -> Result<Vec<Vec<u8>>> {
+	let res = client.get(url).send()?;
+	let der = res.bytes()?;
+	parse_revoked_serials_der(&der)
+}
+
+/// Decodes a DER-encoded `CertificateList`
+/// (https://tools.ietf.org/html/rfc5280#section-5.1) and returns the raw
+/// serial number octets of every entry in `revokedCertificates`.
+pub fn parse_revoked_serials_der(der :&[u8]) -> Result<Vec<Vec<u8>>> {
+	let revoked = yasna::parse_der(der, |rdr| {
+		rdr.read_sequence(|rdr| {
+			let revoked = rdr.next().read_sequence(|rdr| {
+				// version, only present for v2 CRLs
+				rdr.read_optional(|rdr| rdr.read_u64())?;
+				// signature (AlgorithmIdentifier)
+				rdr.next().read_der()?;
+				// issuer
+				rdr.next().read_der()?;
+				// thisUpdate
+				rdr.next().read_der()?;
+				// nextUpdate. Tag-checked against Time's two possible tags
+				// before consuming -- unlike a bare read_der(), which would
+				// happily "read" the following revokedCertificates SEQUENCE
+				// as nextUpdate's content when nextUpdate is legally absent.
+				rdr.read_optional(|rdr| {
+					let tag = rdr.lookahead_tag()?;
+					if tag != TAG_UTCTIME && tag != TAG_GENERALIZEDTIME {
+						return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+					}
+					rdr.read_der()
+				})?;
+				// revokedCertificates
+				let revoked = rdr.read_optional(|rdr| {
+					let mut serials = Vec::new();
+					rdr.read_sequence_of(|rdr| {
+						rdr.read_sequence(|rdr| {
+							let serial = rdr.next().read_der()?;
+							serials.push(cert_ext::integer_content(&serial));
+							// revocationDate
+							rdr.next().read_der()?;
+							// crlEntryExtensions
+							rdr.read_optional(|rdr| rdr.read_der())?;
+							Ok(())
+						})
+					})?;
+					Ok(serials)
+				})?;
+				// crlExtensions [0] EXPLICIT Extensions, unused here
+				rdr.read_optional(|rdr| rdr.read_tagged(Tag::context(0), |rdr| rdr.read_der()))?;
+				Ok(revoked.unwrap_or_default())
+			})?;
+			// signatureAlgorithm
+			rdr.next().read_der()?;
+			// signature
+			rdr.next().read_der()?;
+			Ok(revoked)
+		})
+	})?;
+	Ok(revoked)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Builds a DER tag-length-value triplet for short-form lengths (< 128
+	/// bytes of content), which is all this test needs.
+	fn der_tlv(tag :u8, content :&[u8]) -> Vec<u8> {
+		assert!(content.len() < 128);
+		let mut out = vec![tag, content.len() as u8];
+		out.extend_from_slice(content);
+		out
+	}
+
+	#[test]
+	fn revoked_serials_single_entry() -> Result<()> {
+		let signature_placeholder = der_tlv(0x30, b"");
+		let issuer_placeholder = der_tlv(0x30, b"");
+		let this_update = der_tlv(0x17, b"200101000000Z");
+		let next_update = der_tlv(0x17, b"200201000000Z");
+
+		let serial = der_tlv(0x02, &[42]);
+		let revocation_date = der_tlv(0x17, b"200101000000Z");
+		let revoked_entry = der_tlv(0x30, &[serial, revocation_date].concat());
+		let revoked_certificates = der_tlv(0x30, &revoked_entry);
+
+		let tbs_body :Vec<u8> = [signature_placeholder, issuer_placeholder, this_update, next_update, revoked_certificates].concat();
+		let tbs_cert_list = der_tlv(0x30, &tbs_body);
+
+		let signature_algorithm = der_tlv(0x30, b"");
+		let signature = der_tlv(0x03, &[0x00]);
+		let cert_list_der = der_tlv(0x30, &[tbs_cert_list, signature_algorithm, signature].concat());
+
+		let revoked = parse_revoked_serials_der(&cert_list_der)?;
+		assert_eq!(revoked, vec![vec![42]]);
+		Ok(())
+	}
+
+	// Regression test for nextUpdate being read with a bare read_der(),
+	// which doesn't check the tag: an omitted nextUpdate (legal per RFC
+	// 5280) would get "read" as if it were the following
+	// revokedCertificates SEQUENCE, silently discarding the real one.
+	#[test]
+	fn revoked_serials_no_next_update() -> Result<()> {
+		let signature_placeholder = der_tlv(0x30, b"");
+		let issuer_placeholder = der_tlv(0x30, b"");
+		let this_update = der_tlv(0x17, b"200101000000Z");
+
+		let serial = der_tlv(0x02, &[42]);
+		let revocation_date = der_tlv(0x17, b"200101000000Z");
+		let revoked_entry = der_tlv(0x30, &[serial, revocation_date].concat());
+		let revoked_certificates = der_tlv(0x30, &revoked_entry);
+
+		// No next_update between this_update and revoked_certificates.
+		let tbs_body :Vec<u8> = [signature_placeholder, issuer_placeholder, this_update, revoked_certificates].concat();
+		let tbs_cert_list = der_tlv(0x30, &tbs_body);
+
+		let signature_algorithm = der_tlv(0x30, b"");
+		let signature = der_tlv(0x03, &[0x00]);
+		let cert_list_der = der_tlv(0x30, &[tbs_cert_list, signature_algorithm, signature].concat());
+
+		let revoked = parse_revoked_serials_der(&cert_list_der)?;
+		assert_eq!(revoked, vec![vec![42]]);
+		Ok(())
+	}
+}
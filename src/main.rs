@@ -4,12 +4,17 @@ use ctclient::CTClient;
 use serde::Deserialize;
 use std::{convert::TryInto, io::Write};
 use std::io::Read;
+use std::str::FromStr;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use sha2::{Sha256, Digest};
+use yasna::models::ObjectIdentifier as Oid;
 
 use rocksdb::{DB, Options};
 
 mod cert_ext;
+mod crl;
+mod ct_verify;
+mod output;
 
 #[derive(Deserialize)]
 struct OperatorList {
@@ -70,6 +75,7 @@ enum SubCommand {
 	Filter(FilterOpts),
 	LiveStream(LstrOpts),
 	Scan(ScanOpts),
+	Status(StatusOpts),
 }
 
 #[derive(Clap)]
@@ -85,16 +91,64 @@ struct DlOpts {
 	end :u64,
 }
 
+#[derive(Clap)]
+struct StatusOpts {
+	url :String,
+}
+
 #[derive(Clap)]
 struct FilterOpts {
 	url :String,
 	start :u64,
 	end :u64,
+	/// Only report name-constraints matches whose permitted/excluded
+	/// subtrees touch this domain (exact match, a subdomain or a parent
+	/// domain of it).
+	#[clap(long)]
+	constrains_domain :Option<String>,
+	/// Report certs whose subjectAltName contains a dNSName matching this
+	/// pattern, e.g. "example.com" or "*.example.com". If `--oid` or
+	/// `--constrains-domain` is also given, certs matching those are
+	/// reported as well.
+	#[clap(long)]
+	dns :Option<String>,
+	/// For each match, download the cert's CRL (from its CRL distribution
+	/// point) and report whether the cert's serial number is listed on it.
+	#[clap(long)]
+	check_revoked :bool,
+	/// OID to search for, in dotted-decimal form (e.g. "2.5.29.30").
+	/// Repeatable. Defaults to id-ce-nameConstraints if none are given.
+	#[clap(long)]
+	oid :Vec<String>,
+	/// Whether a cert must carry all given `--oid`s ("all") or just one of
+	/// them ("any") to be reported.
+	#[clap(long, default_value = "any")]
+	r#match :String,
+	/// Only match extensions marked critical.
+	#[clap(long)]
+	critical_only :bool,
+	/// Output format for matched entries: text, json or ndjson.
+	#[clap(long, default_value = "text")]
+	format :String,
 }
 
 #[derive(Clap)]
 struct LstrOpts {
 	url :String,
+	/// OID to search for, in dotted-decimal form (e.g. "2.5.29.30").
+	/// Repeatable. Defaults to id-ce-nameConstraints if none are given.
+	#[clap(long)]
+	oid :Vec<String>,
+	/// Whether a cert must carry all given `--oid`s ("all") or just one of
+	/// them ("any") to be reported.
+	#[clap(long, default_value = "any")]
+	r#match :String,
+	/// Only match extensions marked critical.
+	#[clap(long)]
+	critical_only :bool,
+	/// Output format for matched entries: text, json or ndjson.
+	#[clap(long, default_value = "text")]
+	format :String,
 }
 
 #[derive(Clap)]
@@ -102,6 +156,35 @@ struct ScanOpts {
 	url :String,
 	start :u64,
 	end :u64,
+	/// Only report name-constraints matches whose permitted/excluded
+	/// subtrees touch this domain (exact match, a subdomain or a parent
+	/// domain of it).
+	#[clap(long)]
+	constrains_domain :Option<String>,
+	/// Report certs whose subjectAltName contains a dNSName matching this
+	/// pattern, e.g. "example.com" or "*.example.com". If `--oid` or
+	/// `--constrains-domain` is also given, certs matching those are
+	/// reported as well.
+	#[clap(long)]
+	dns :Option<String>,
+	/// For each match, download the cert's CRL (from its CRL distribution
+	/// point) and report whether the cert's serial number is listed on it.
+	#[clap(long)]
+	check_revoked :bool,
+	/// OID to search for, in dotted-decimal form (e.g. "2.5.29.30").
+	/// Repeatable. Defaults to id-ce-nameConstraints if none are given.
+	#[clap(long)]
+	oid :Vec<String>,
+	/// Whether a cert must carry all given `--oid`s ("all") or just one of
+	/// them ("any") to be reported.
+	#[clap(long, default_value = "any")]
+	r#match :String,
+	/// Only match extensions marked critical.
+	#[clap(long)]
+	critical_only :bool,
+	/// Output format for matched entries: text, json or ndjson.
+	#[clap(long, default_value = "text")]
+	format :String,
 }
 
 fn dl_range(url :&str, op_start :u64, op_end :u64, mut f :impl FnMut(u64, EntriesResult) -> Result<()>) -> Result<()> {
@@ -132,6 +215,35 @@ fn dl_range(url :&str, op_start :u64, op_end :u64, mut f :impl FnMut(u64, Entrie
 	Ok(())
 }
 
+// Distinct from the `1`-prefixed entry keys, so it can't collide with them.
+const PROGRESS_KEY :&[u8] = &[0u8];
+
+/// A log DB's download progress: the highest index such that every
+/// entry `0..=that index` has been downloaded (`None` if nothing has been
+/// mirrored yet), and the tree size of the last STH we verified against.
+fn read_progress(db :&DB) -> Result<(Option<u64>, u64)> {
+	let value = db.get(PROGRESS_KEY)?;
+	let value = if let Some(v) = value { v } else { return Ok((None, 0)) };
+	let mut rdr = value.as_slice();
+	let has_contiguous = rdr.read_u8()? != 0;
+	let highest_contiguous = rdr.read_u64::<BigEndian>()?;
+	let tree_size = rdr.read_u64::<BigEndian>()?;
+	Ok((if has_contiguous { Some(highest_contiguous) } else { None }, tree_size))
+}
+
+fn write_progress(db :&DB, highest_contiguous :Option<u64>, tree_size :u64) -> Result<()> {
+	let mut value = Vec::new();
+	value.write_u8(highest_contiguous.is_some() as u8)?;
+	value.write_u64::<BigEndian>(highest_contiguous.unwrap_or(0))?;
+	value.write_u64::<BigEndian>(tree_size)?;
+	db.put(PROGRESS_KEY, &value)?;
+	Ok(())
+}
+
+fn parse_format(s :&str) -> Result<output::OutputFormat> {
+	s.parse().map_err(|e :String| anyhow::anyhow!(e))
+}
+
 fn get_matching_log(url :&str) -> Result<Log> {
 	let operators = obtain_all_operator_list()?;
 	let log = operators.operators.iter().map(|op| op.logs.iter())
@@ -154,13 +266,154 @@ static USER_AGENT :&str = concat!("ct-ext-search ", env!("CARGO_PKG_VERSION"),
 const OID_EXT_KEY_USAGE :&[u64] = &[2, 5, 29, 37];
 
 // id-ce-nameConstraints in
-/// https://tools.ietf.org/html/rfc5280#section-4.2.1.10
-const OID_NAME_CONSTRAINTS :&[u64] = &[2, 5, 29, 30];
+// https://tools.ietf.org/html/rfc5280#section-4.2.1.10
+use cert_ext::OID_NAME_CONSTRAINTS;
+
+/// How a cert's extensions are matched against the `--oid` filter set,
+/// selected via `--match`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OidMatchMode {
+	/// At least one of the given OIDs must be present.
+	Any,
+	/// Every given OID must be present.
+	All,
+}
+
+impl FromStr for OidMatchMode {
+	type Err = String;
+
+	fn from_str(s :&str) -> Result<Self, Self::Err> {
+		match s {
+			"any" => Ok(OidMatchMode::Any),
+			"all" => Ok(OidMatchMode::All),
+			other => Err(format!("Unknown match mode '{}'. Valid values: any, all", other)),
+		}
+	}
+}
+
+fn parse_match_mode(s :&str) -> Result<OidMatchMode> {
+	s.parse().map_err(|e :String| anyhow::anyhow!(e))
+}
+
+/// Parses the `--oid` values (dotted decimal, e.g. "2.5.29.30") into
+/// `ObjectIdentifier`s. Defaults to `[id-ce-nameConstraints]` if none
+/// were given, to keep `--constrains-domain` useful without `--oid`.
+fn parse_oid_filters(oids :&[String]) -> Result<Vec<Oid>> {
+	if oids.is_empty() {
+		return Ok(vec![Oid::from_slice(OID_NAME_CONSTRAINTS)]);
+	}
+	oids.iter().map(|s| {
+		let components = s.split('.')
+			.map(|c| c.parse::<u64>().map_err(|_| anyhow::anyhow!("Invalid OID '{}': '{}' is not a number", s, c)))
+			.collect::<Result<Vec<u64>>>()?;
+		Ok(Oid::from_slice(&components))
+	}).collect()
+}
+
+/// Whether `infos` satisfies the `--oid`/`--match`/`--critical-only`
+/// filter set, additionally gating an `id-ce-nameConstraints` match on
+/// `--constrains-domain` if that was given.
+fn oids_match(entry :&Entry, der :&[u8], infos :&[cert_ext::ExtensionInfo], filters :&[Oid], mode :OidMatchMode, critical_only :bool, constrains_domain :&Option<String>) -> Result<bool> {
+	let present = |filter :&Oid| -> Result<bool> {
+		let found = infos.iter().any(|info| &info.oid == filter && (!critical_only || info.critical));
+		if !found {
+			return Ok(false);
+		}
+		name_constraints_match(entry, der, filter.components(), constrains_domain)
+	};
+	match mode {
+		OidMatchMode::Any => {
+			for filter in filters {
+				if present(filter)? {
+					return Ok(true);
+				}
+			}
+			Ok(false)
+		},
+		OidMatchMode::All => {
+			for filter in filters {
+				if !present(filter)? {
+					return Ok(false);
+				}
+			}
+			Ok(true)
+		},
+	}
+}
+
+/// Whether a name-constraints match should be reported, given an optional
+/// `--constrains-domain` filter. Other OIDs always pass through unfiltered.
+fn name_constraints_match(entry :&Entry, der :&[u8], ioid :&[u64], constrains_domain :&Option<String>) -> Result<bool> {
+	let domain = if let Some(domain) = constrains_domain { domain } else { return Ok(true) };
+	if ioid != OID_NAME_CONSTRAINTS {
+		return Ok(true);
+	}
+	let extn_value = match entry {
+		Entry::X509Entry(_) => cert_ext::find_extension_der(der, ioid)?,
+		Entry::PrecertEntry(..) => cert_ext::find_pre_cert_extension_der(der, ioid)?,
+	};
+	let extn_value = if let Some(v) = extn_value { v } else { return Ok(false) };
+	let nc = cert_ext::parse_name_constraints_der(&extn_value)?;
+	Ok(cert_ext::name_constraints_touch_domain(&nc, domain))
+}
+
+/// Whether the cert's subjectAltName contains a dNSName matching `pattern`.
+fn dns_search_match(entry :&Entry, der :&[u8], pattern :&str) -> Result<bool> {
+	let extn_value = match entry {
+		Entry::X509Entry(_) => cert_ext::find_extension_der(der, cert_ext::OID_SUBJECT_ALT_NAME)?,
+		Entry::PrecertEntry(..) => cert_ext::find_pre_cert_extension_der(der, cert_ext::OID_SUBJECT_ALT_NAME)?,
+	};
+	let extn_value = if let Some(v) = extn_value { v } else { return Ok(false) };
+	let names = cert_ext::parse_subject_alt_names_der(&extn_value)?;
+	Ok(cert_ext::dns_names_match(&names, pattern))
+}
+
+/// Whether an entry should be reported, combining the `--oid` filter and
+/// the `--dns` search into a single yes/no so callers print at most one
+/// `MatchRecord` per entry. The default `id-ce-nameConstraints` OID filter
+/// only applies when the user didn't ask for an independent `--dns`
+/// search, or when they opted into OID matching explicitly (`--oid` or
+/// `--constrains-domain`) -- otherwise a pure `--dns` search would also
+/// report every nameConstraints-bearing cert it happens to pass over.
+fn entry_matches(entry :&Entry, der :&[u8], infos :&[cert_ext::ExtensionInfo], oid_filters :&[Oid], match_mode :OidMatchMode, critical_only :bool, oid_requested :bool, constrains_domain :&Option<String>, dns_pattern :&Option<String>) -> Result<bool> {
+	let oid_filter_active = oid_requested || constrains_domain.is_some() || dns_pattern.is_none();
+	if oid_filter_active && oids_match(entry, der, infos, oid_filters, match_mode, critical_only, constrains_domain)? {
+		return Ok(true);
+	}
+	if let Some(pattern) = dns_pattern {
+		if dns_search_match(entry, der, pattern)? {
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
 
-static INTERESTING_OIDS :&[&[u64]] = &[
-	//OID_EXT_KEY_USAGE,
-	OID_NAME_CONSTRAINTS,
-];
+/// When `--check-revoked` was given, looks up the cert's CRL distribution
+/// points, downloads the first `http(s)://` one it finds and checks
+/// whether the cert's serial number is listed as revoked on it. Returns
+/// `None` if the check wasn't requested, or the cert has no CRL
+/// distribution point we can fetch.
+fn check_revocation(client :&reqwest::blocking::Client, entry :&Entry, der :&[u8], check_revoked :bool) -> Result<Option<bool>> {
+	if !check_revoked {
+		return Ok(None);
+	}
+	let extn_value = match entry {
+		Entry::X509Entry(_) => cert_ext::find_extension_der(der, cert_ext::OID_CRL_DISTRIBUTION_POINTS)?,
+		Entry::PrecertEntry(..) => cert_ext::find_pre_cert_extension_der(der, cert_ext::OID_CRL_DISTRIBUTION_POINTS)?,
+	};
+	let extn_value = if let Some(v) = extn_value { v } else { return Ok(None) };
+	let names = cert_ext::parse_crl_distribution_points_der(&extn_value)?;
+	let url = names.iter().find_map(|name| match name {
+		cert_ext::GeneralName::Uri(u) if u.starts_with("http://") || u.starts_with("https://") => Some(u.clone()),
+		_ => None,
+	});
+	let url = if let Some(url) = url { url } else { return Ok(None) };
+	let serial = match entry {
+		Entry::X509Entry(_) => cert_ext::cert_serial_number_der(der)?,
+		Entry::PrecertEntry(..) => cert_ext::pre_cert_serial_number_der(der)?,
+	};
+	Ok(Some(crl::is_revoked(client, &url, &serial)?))
+}
 
 struct TimestampedEntry {
 	timestamp :u64,
@@ -289,37 +542,72 @@ fn main() -> Result<()> {
 			println!("Found log '{}' matching URL", log.description);
 			let public_key = base64::decode(&log.key).unwrap();
 			let mut hasher = Sha256::new();
-			hasher.update(public_key);
+			hasher.update(&public_key);
 			let pubkey_hash = hasher.finalize();
 			let db_path = format!("db/{}.db", hex::encode(pubkey_hash));
 			let mut db_opts = Options::default();
 			db_opts.create_if_missing(true);
 			let db = DB::open(&db_opts, db_path)?;
-			dl_range(&opts.url,opts.start, opts.end, |start , entry_result| {
-				for (id, entry) in entry_result.entries.iter().enumerate() {
-					let id = start + id as u64;
-					let mut db_value = Vec::new();
-					let leaf_input_raw = base64::decode(&entry.leaf_input)?;
-					let extra_data_raw = base64::decode(&entry.extra_data)?;
-					db_value.write_u64::<BigEndian>(leaf_input_raw.len() as u64).unwrap();
-					db_value.write_all(&leaf_input_raw).unwrap();
-					db_value.write_u64::<BigEndian>(extra_data_raw.len() as u64).unwrap();
-					db_value.write_all(&extra_data_raw).unwrap();
-					let mut key = Vec::with_capacity(9);
-					key.push(1);
-					key.extend_from_slice(&id.to_be_bytes());
-					db.put(&key, &db_value)?;
-				}
-				Ok(())
-			})?;
 
-			/*let client = reqwest::blocking::Client::builder()
+			let client = reqwest::blocking::Client::builder()
 				.user_agent(USER_AGENT)
 				.build()?;
-			let res = client.get(&format!("{}/ct/v1/get-sth", opts.url)).send()?;
-			println!("{}", res.text()?);*/
+			let sth = ct_verify::fetch_sth(&client, &opts.url, &public_key)?;
+			println!("Verified STH: tree_size={}, timestamp={}", sth.tree_size, sth.timestamp);
+
+			let (mut highest_contiguous, _) = read_progress(&db)?;
+			let next_needed = highest_contiguous.map_or(0, |h| h + 1);
+			let start = opts.start.max(next_needed);
+			let end = opts.end.min(sth.tree_size.saturating_sub(1));
+			let has_gap = start > next_needed;
+			if has_gap {
+				println!("Warning: requested start {} is ahead of the highest contiguously \
+					mirrored index ({}); progress tracking won't advance until that gap is filled.",
+					start, next_needed);
+			}
+			if start > end {
+				println!("Nothing to do: already mirrored up to the requested range.");
+			} else {
+				dl_range(&opts.url, start, end, |batch_start, entry_result| {
+					for (id, entry) in entry_result.entries.iter().enumerate() {
+						let id = batch_start + id as u64;
+						let leaf_input_raw = base64::decode(&entry.leaf_input)?;
+						let extra_data_raw = base64::decode(&entry.extra_data)?;
+
+						let verified = match ct_verify::verify_leaf_inclusion(&client, &opts.url, &leaf_input_raw, id, &sth) {
+							Ok(()) => true,
+							Err(e) => {
+								eprintln!("Warning: couldn't verify inclusion proof for entry {}: {}", id, e);
+								false
+							},
+						};
+
+						let mut db_value = Vec::new();
+						db_value.write_u8(verified as u8).unwrap();
+						db_value.write_u64::<BigEndian>(leaf_input_raw.len() as u64).unwrap();
+						db_value.write_all(&leaf_input_raw).unwrap();
+						db_value.write_u64::<BigEndian>(extra_data_raw.len() as u64).unwrap();
+						db_value.write_all(&extra_data_raw).unwrap();
+						let mut key = Vec::with_capacity(9);
+						key.push(1);
+						key.extend_from_slice(&id.to_be_bytes());
+						db.put(&key, &db_value)?;
+					}
+					// dl_range downloads sequentially with no internal gaps, so as long as we
+					// didn't start past a pre-existing gap, this batch extends contiguity.
+					if !has_gap && !entry_result.entries.is_empty() {
+						let batch_end = batch_start + entry_result.entries.len() as u64 - 1;
+						highest_contiguous = Some(batch_end);
+						write_progress(&db, highest_contiguous, sth.tree_size)?;
+					}
+					Ok(())
+				})?;
+			}
 		},
 		SubCommand::Filter(opts) => {
+			let format = parse_format(&opts.format)?;
+			let oid_filters = parse_oid_filters(&opts.oid)?;
+			let match_mode = parse_match_mode(&opts.r#match)?;
 			println!("Filtering log with url {}", opts.url);
 			let log = get_matching_log(&opts.url)?;
 			println!("Found log '{}' matching URL", log.description);
@@ -329,6 +617,9 @@ fn main() -> Result<()> {
 			let pubkey_hash = hasher.finalize();
 			let db_path = format!("db/{}.db", hex::encode(pubkey_hash));
 			let db = DB::open_default(db_path)?;
+			let client = reqwest::blocking::Client::builder()
+				.user_agent(USER_AGENT)
+				.build()?;
 			for id in opts.start ..= opts.end {
 				let mut key = Vec::with_capacity(9);
 				key.push(1);
@@ -342,36 +633,51 @@ fn main() -> Result<()> {
 					break;
 				};
 				let mut val_rdr = db_value.as_slice();
+				let verified = val_rdr.read_u8()? != 0;
 				let leaf_input_raw_len = val_rdr.read_u64::<BigEndian>()?;
 				let mut leaf_input_raw = vec![0; leaf_input_raw_len as usize];
 				val_rdr.read_exact(&mut leaf_input_raw)?;
 				let extra_data_raw_len = val_rdr.read_u64::<BigEndian>()?;
 				let mut extra_data_raw = vec![0; extra_data_raw_len as usize];
 				val_rdr.read_exact(&mut extra_data_raw)?;
+				if !verified {
+					println!("Warning: entry {} has no verified inclusion proof.", id);
+				}
 
 				let entry = parse_timestamped_entry(&leaf_input_raw)?;
-				let (oids, der) = match &entry.signed_entry {
+				let (infos, der) = match &entry.signed_entry {
 					Entry::X509Entry(der) => {
-						(cert_ext::list_cert_extensions_der(der)?, der)
+						(cert_ext::list_cert_extension_infos_der(der)?, der)
 					},
 					Entry::PrecertEntry(_issuer_key_hash, der) => {
-						(cert_ext::list_pre_cert_extensions_der(der)?, der)
+						(cert_ext::list_pre_cert_extension_infos_der(der)?, der)
 					},
 				};
 				let log_entry = read_log_entry(&extra_data_raw)?;
-				for oid in oids {
-					for ioid in INTERESTING_OIDS {
-						if ioid == oid.components() {
-							let chain :String = log_entry.chain.iter()
-								.enumerate()
-								.map(|(i, c)| format!("\n  --> Chain entry {}: {} ", i, base64::encode(&c))).collect();
-							println!("Match found. Base64: {}. {}", base64::encode(&der), chain);
-						}
-					}
+				let matched = match entry_matches(&entry.signed_entry, der, &infos, &oid_filters, match_mode, opts.critical_only, !opts.oid.is_empty(), &opts.constrains_domain, &opts.dns) {
+					Ok(m) => m,
+					Err(e) => {
+						eprintln!("Warning: couldn't check entry {} against the filters: {}", id, e);
+						false
+					},
+				};
+				if matched {
+					let revoked = match check_revocation(&client, &entry.signed_entry, der, opts.check_revoked) {
+						Ok(r) => r,
+						Err(e) => {
+							eprintln!("Warning: couldn't check revocation status for entry {}: {}", id, e);
+							None
+						},
+					};
+					output::MatchRecord::build(Some(id), Some(entry.timestamp), &entry.signed_entry, der, &log_entry.chain, Some(verified), revoked)?
+						.print(format)?;
 				}
 			}
 		},
 		SubCommand::LiveStream(opts) => {
+			let format = parse_format(&opts.format)?;
+			let oid_filters = parse_oid_filters(&opts.oid)?;
+			let match_mode = parse_match_mode(&opts.r#match)?;
 			let log = get_matching_log(&opts.url)?;
 			println!("Found log '{}' matching URL", log.description);
 			let mut ctr = 0u64;
@@ -387,19 +693,23 @@ fn main() -> Result<()> {
 							println!("Reached {} many certs", ctr);
 						}
 						let der = c.to_der().unwrap();
-						let oids = cert_ext::list_cert_extensions_der(&der).unwrap();
-						for oid in oids {
-							for ioid in INTERESTING_OIDS {
-								if ioid == oid.components() {
-									match_found = true;
-								}
-							}
+						let infos = cert_ext::list_cert_extension_infos_der(&der).unwrap();
+						let entry = Entry::X509Entry(der.clone());
+						if oids_match(&entry, &der, &infos, &oid_filters, match_mode, opts.critical_only, &None).unwrap() {
+							match_found = true;
 						}
 					}
 					if match_found {
-						println!("Match found. Chain:");
-						for c in certs {
-							println!("{}", String::from_utf8(c.to_pem().unwrap()).unwrap());
+						// The CT 1.0 API gives us the leaf followed by the rest of the chain.
+						let leaf_der = certs[0].to_der().unwrap();
+						let chain :Vec<Vec<u8>> = certs[1..].iter().map(|c| c.to_der().unwrap()).collect();
+						let leaf_entry = Entry::X509Entry(leaf_der.clone());
+						// CTClient already verified an inclusion proof for these certs as
+						// part of its STH update, so we can report them as verified.
+						let record = output::MatchRecord::build(None, None, &leaf_entry, &leaf_der, &chain, Some(true), None)
+							.and_then(|r| r.print(format));
+						if let Err(e) = record {
+							eprintln!("Error reporting match: {}", e);
 						}
 					}
 				}));
@@ -413,31 +723,89 @@ fn main() -> Result<()> {
 			if opts.start > opts.end {
 				bail!("Start is not before end: {} > {}", opts.start, opts.end);
 			}
+			let format = parse_format(&opts.format)?;
+			let oid_filters = parse_oid_filters(&opts.oid)?;
+			let match_mode = parse_match_mode(&opts.r#match)?;
 			println!("Downloading from log at {}", opts.url);
 
-			dl_range(&opts.url, opts.start,opts.end, |_start, entries_res| {
-				for entry in entries_res.entries {
-					let leaf_input_buf = base64::decode(&entry.leaf_input)?;
+			let log = get_matching_log(&opts.url)?;
+			let public_key = base64::decode(&log.key).unwrap();
+			let client = reqwest::blocking::Client::builder()
+				.user_agent(USER_AGENT)
+				.build()?;
+			let sth = ct_verify::fetch_sth(&client, &opts.url, &public_key)?;
+			println!("Verified STH: tree_size={}, timestamp={}", sth.tree_size, sth.timestamp);
+
+			dl_range(&opts.url, opts.start,opts.end, |start, entries_res| {
+				for (i, raw_entry) in entries_res.entries.iter().enumerate() {
+					let id = start + i as u64;
+					let leaf_input_buf = base64::decode(&raw_entry.leaf_input)?;
+					let extra_data_buf = base64::decode(&raw_entry.extra_data)?;
 					let entry = parse_timestamped_entry(&leaf_input_buf)?;
-					let (oids, der) = match &entry.signed_entry {
+					let log_entry = read_log_entry(&extra_data_buf)?;
+					let verified = match ct_verify::verify_leaf_inclusion(&client, &opts.url, &leaf_input_buf, id, &sth) {
+						Ok(()) => true,
+						Err(e) => {
+							eprintln!("Warning: couldn't verify inclusion proof for entry {}: {}", id, e);
+							false
+						},
+					};
+					let (infos, der) = match &entry.signed_entry {
 						Entry::X509Entry(der) => {
-							(cert_ext::list_cert_extensions_der(der)?, der)
+							(cert_ext::list_cert_extension_infos_der(der)?, der)
 						},
 						Entry::PrecertEntry(_issuer_key_hash, der) => {
-							(cert_ext::list_pre_cert_extensions_der(der)?, der)
+							(cert_ext::list_pre_cert_extension_infos_der(der)?, der)
 						},
 					};
-					for oid in oids {
-						for ioid in INTERESTING_OIDS {
-							if ioid == oid.components() {
-								println!("Match found. Base64: {}", base64::encode(&der));
-							}
-						}
+					let matched = match entry_matches(&entry.signed_entry, der, &infos, &oid_filters, match_mode, opts.critical_only, !opts.oid.is_empty(), &opts.constrains_domain, &opts.dns) {
+						Ok(m) => m,
+						Err(e) => {
+							eprintln!("Warning: couldn't check entry {} against the filters: {}", id, e);
+							false
+						},
+					};
+					if matched {
+						let revoked = match check_revocation(&client, &entry.signed_entry, der, opts.check_revoked) {
+							Ok(r) => r,
+							Err(e) => {
+								eprintln!("Warning: couldn't check revocation status for entry {}: {}", id, e);
+								None
+							},
+						};
+						output::MatchRecord::build(Some(id), Some(entry.timestamp), &entry.signed_entry, der, &log_entry.chain, Some(verified), revoked)?
+							.print(format)?;
 					}
 				}
 				Ok(())
 			})?;
 		},
+		SubCommand::Status(opts) => {
+			let log = get_matching_log(&opts.url)?;
+			let public_key = base64::decode(&log.key).unwrap();
+			let mut hasher = Sha256::new();
+			hasher.update(&public_key);
+			let pubkey_hash = hasher.finalize();
+			let db_path = format!("db/{}.db", hex::encode(pubkey_hash));
+			let db = DB::open_default(db_path)?;
+			let (highest_contiguous, _) = read_progress(&db)?;
+
+			let client = reqwest::blocking::Client::builder()
+				.user_agent(USER_AGENT)
+				.build()?;
+			let sth = ct_verify::fetch_sth(&client, &opts.url, &public_key)?;
+
+			match highest_contiguous {
+				Some(highest) => {
+					let remaining = sth.tree_size.saturating_sub(highest + 1);
+					println!("Log '{}': mirrored 0..={} ({} remaining of {} live entries)",
+						log.description, highest, remaining, sth.tree_size);
+				},
+				None => {
+					println!("Log '{}': nothing mirrored yet ({} live entries)", log.description, sth.tree_size);
+				},
+			}
+		},
     }
 	Ok(())
 }
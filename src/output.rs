@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::cert_ext;
+
+/// Output format for matched entries, selected via `--format`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+	Text,
+	Json,
+	Ndjson,
+}
+
+impl FromStr for OutputFormat {
+	type Err = String;
+
+	fn from_str(s :&str) -> Result<Self, Self::Err> {
+		match s {
+			"text" => Ok(OutputFormat::Text),
+			"json" => Ok(OutputFormat::Json),
+			"ndjson" => Ok(OutputFormat::Ndjson),
+			other => Err(format!("Unknown output format '{}'. Valid values: text, json, ndjson", other)),
+		}
+	}
+}
+
+impl Default for OutputFormat {
+	fn default() -> Self {
+		OutputFormat::Text
+	}
+}
+
+#[derive(Serialize)]
+pub struct ExtensionRecord {
+	pub oid :String,
+	pub critical :bool,
+	pub label :Option<&'static str>,
+}
+
+/// A single matched CT log entry, ready to be printed in whichever
+/// `OutputFormat` the user asked for.
+#[derive(Serialize)]
+pub struct MatchRecord {
+	/// The entry's index in the log's Merkle tree, if known.
+	pub entry_id :Option<u64>,
+	pub timestamp :Option<u64>,
+	pub entry_type :&'static str,
+	pub issuer_key_hash :Option<String>,
+	pub extensions :Vec<ExtensionRecord>,
+	pub cert_base64 :String,
+	pub chain_base64 :Vec<String>,
+	/// Whether a Merkle inclusion proof for this entry was fetched and
+	/// verified against a signed tree head. `None` if verification wasn't
+	/// attempted.
+	pub verified :Option<bool>,
+	/// Whether the cert's serial number was found on its CRL distribution
+	/// point's CRL. `None` if `--check-revoked` wasn't passed, or the cert
+	/// has no usable CRL distribution point.
+	pub revoked :Option<bool>,
+}
+
+impl MatchRecord {
+	pub fn build(entry_id :Option<u64>, timestamp :Option<u64>, signed_entry :&crate::Entry, der :&[u8], chain :&[Vec<u8>], verified :Option<bool>, revoked :Option<bool>) -> Result<MatchRecord> {
+		let (entry_type, issuer_key_hash, extension_infos) = match signed_entry {
+			crate::Entry::X509Entry(_) => ("x509", None, cert_ext::list_cert_extension_infos_der(der)?),
+			crate::Entry::PrecertEntry(issuer_key_hash, _) =>
+				("precert", Some(hex::encode(issuer_key_hash)), cert_ext::list_pre_cert_extension_infos_der(der)?),
+		};
+		let extensions = extension_infos.into_iter().map(|ext| ExtensionRecord {
+			oid : ext.oid.components().iter().map(u64::to_string).collect::<Vec<_>>().join("."),
+			critical : ext.critical,
+			label : cert_ext::oid_label(&ext.oid),
+		}).collect();
+		Ok(MatchRecord {
+			entry_id,
+			timestamp,
+			entry_type,
+			issuer_key_hash,
+			extensions,
+			cert_base64 : base64::encode(der),
+			chain_base64 : chain.iter().map(base64::encode).collect(),
+			verified,
+			revoked,
+		})
+	}
+
+	pub fn print(&self, format :OutputFormat) -> Result<()> {
+		match format {
+			OutputFormat::Text => {
+				let chain :String = self.chain_base64.iter()
+					.enumerate()
+					.map(|(i, c)| format!("\n  --> Chain entry {}: {} ", i, c)).collect();
+				if self.verified == Some(false) {
+					println!("Match found (WARNING: inclusion proof verification failed). Base64: {}. {}", self.cert_base64, chain);
+				} else if self.revoked == Some(true) {
+					println!("Match found (WARNING: cert appears on its CRL as revoked). Base64: {}. {}", self.cert_base64, chain);
+				} else {
+					println!("Match found. Base64: {}. {}", self.cert_base64, chain);
+				}
+			},
+			OutputFormat::Json => println!("{}", serde_json::to_string_pretty(self)?),
+			OutputFormat::Ndjson => println!("{}", serde_json::to_string(self)?),
+		}
+		Ok(())
+	}
+}